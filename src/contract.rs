@@ -0,0 +1,278 @@
+//! Adapter that lets a [`Manager`] be used as a cw-multi-test `Contract`.
+
+use crate::manager::Manager;
+use crate::response::Response;
+use cosmwasm_std::{Binary, Deps, DepsMut, Empty, Env, MessageInfo, Reply};
+use cw_multi_test::Contract;
+use serde::Deserialize;
+use serde_json::Value;
+use std::cell::RefCell;
+
+/// The envelope a [`ManagerContract`] expects for `execute`, `sudo` and
+/// `migrate` calls, since those entry points must route to a single one of
+/// the `Manager`'s registered modules rather than to all of them.
+#[derive(Deserialize)]
+struct ModuleMsg {
+    /// The registered module's [`crate::module::GenericModule::name`].
+    module: String,
+    /// The message to dispatch to that module.
+    msg: Value,
+}
+
+/// The envelope a [`ManagerContract`] expects for `query` calls. Unlike
+/// `execute`/`sudo`/`migrate`, a query can either dispatch to a single
+/// module like those do, or ask for the merged JSON schema of every
+/// module this `Manager` hosts, so that tooling has a single chain query
+/// it can call to discover the message shapes of a dynamically dispatched
+/// contract.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum QueryMsg {
+    /// Dispatches to a single registered module, same as `execute`/`sudo`/
+    /// `migrate`.
+    Dispatch(ModuleMsg),
+    /// Returns [`Manager::schema`] for every module registered with this
+    /// `Manager`, keyed by module name.
+    Schema {},
+}
+
+/// Wraps a [`Manager`] so it can be registered with a cw-multi-test `App`
+/// via `App::store_code` and exercised as a `Box<dyn Contract<Empty>>`.
+///
+/// This lets composed modules be driven through multi-contract test flows
+/// — submessages, bank interactions, cross-contract calls — entirely
+/// in-process, without the modules needing to speak `WasmMsg`/`AppResponse`
+/// themselves.
+///
+/// `Manager`'s dispatch methods take `&mut self` to let modules mutate
+/// their own in-memory state, but `cw_multi_test::Contract` only hands out
+/// `&self`, so the `Manager` is kept behind a `RefCell`.
+pub struct ManagerContract {
+    manager: RefCell<Manager>,
+}
+
+impl ManagerContract {
+    /// Wraps `manager` so it can be stored as a `Box<dyn Contract<Empty>>`.
+    pub fn new(manager: Manager) -> Self {
+        Self {
+            manager: RefCell::new(manager),
+        }
+    }
+}
+
+impl Contract<Empty> for ManagerContract {
+    fn instantiate(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: Vec<u8>,
+    ) -> anyhow::Result<Response> {
+        let msgs = serde_json::from_slice(&msg)?;
+        self.manager
+            .borrow_mut()
+            .instantiate(&mut deps, &env, &info, &msgs)
+            .map_err(anyhow::Error::msg)
+    }
+
+    fn execute(
+        &self,
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: Vec<u8>,
+    ) -> anyhow::Result<Response> {
+        let ModuleMsg { module, msg } = serde_json::from_slice(&msg)?;
+        self.manager
+            .borrow_mut()
+            .execute(&mut deps, env, info, &module, &msg)
+            .map_err(anyhow::Error::msg)
+    }
+
+    fn query(&self, deps: Deps, env: Env, msg: Vec<u8>) -> anyhow::Result<Binary> {
+        match serde_json::from_slice(&msg)? {
+            QueryMsg::Dispatch(ModuleMsg { module, msg }) => self
+                .manager
+                .borrow()
+                .query(&deps, env, &module, &msg)
+                .map_err(anyhow::Error::msg),
+            QueryMsg::Schema {} => {
+                cosmwasm_std::to_binary(&self.manager.borrow().schema()).map_err(anyhow::Error::msg)
+            }
+        }
+    }
+
+    fn sudo(&self, mut deps: DepsMut, env: Env, msg: Vec<u8>) -> anyhow::Result<Response> {
+        let ModuleMsg { module, msg } = serde_json::from_slice(&msg)?;
+        self.manager
+            .borrow_mut()
+            .sudo(&mut deps, env, &module, &msg)
+            .map_err(anyhow::Error::msg)
+    }
+
+    fn reply(&self, mut deps: DepsMut, env: Env, reply: Reply) -> anyhow::Result<Response> {
+        self.manager
+            .borrow_mut()
+            .reply(&mut deps, env, reply)
+            .map_err(anyhow::Error::msg)
+    }
+
+    fn migrate(&self, mut deps: DepsMut, env: Env, msg: Vec<u8>) -> anyhow::Result<Response> {
+        let ModuleMsg { module, msg } = serde_json::from_slice(&msg)?;
+        self.manager
+            .borrow_mut()
+            .migrate(&mut deps, env, &module, &msg)
+            .map_err(anyhow::Error::msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manager::Manager;
+    use crate::module::Module;
+    use cosmwasm_std::{BankMsg, CosmosMsg, StdError, SubMsg};
+    use cw_multi_test::{App, Executor};
+    use std::collections::HashMap;
+
+    const REPLIED_KEY: &[u8] = b"replied";
+
+    /// A [`Module`] that dispatches a reply-requesting submessage from
+    /// `instantiate` and, when the reply comes back, marks the fact in the
+    /// chain's own storage. Exercising it through a real `cw_multi_test::App`
+    /// drives `ManagerContract` through instantiate, a submessage + reply,
+    /// execute, query, sudo and migrate — every entry point `Contract`
+    /// forwards to the `Manager`.
+    struct EchoModule;
+
+    impl Module for EchoModule {
+        type InstantiateMsg = Empty;
+        type ExecuteMsg = Empty;
+        type QueryMsg = Empty;
+        type QueryResp = bool;
+        type Error = StdError;
+        type SudoMsg = Empty;
+        type MigrateMsg = Empty;
+
+        fn instantiate(
+            &mut self,
+            _deps: &mut DepsMut,
+            env: &Env,
+            _info: &MessageInfo,
+            _msg: Empty,
+        ) -> Result<Response, StdError> {
+            Ok(Response::new().add_submessage(SubMsg::reply_always(
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: env.contract.address.to_string(),
+                    amount: vec![],
+                }),
+                1,
+            )))
+        }
+
+        fn execute(
+            &mut self,
+            _deps: &mut DepsMut,
+            _env: Env,
+            _info: MessageInfo,
+            _msg: Empty,
+        ) -> Result<Response, StdError> {
+            Ok(Response::new())
+        }
+
+        fn query(&self, deps: &Deps, _env: Env, _msg: Empty) -> Result<bool, StdError> {
+            Ok(deps.storage.get(REPLIED_KEY).is_some())
+        }
+
+        fn reply(
+            &mut self,
+            deps: &mut DepsMut,
+            _env: Env,
+            _reply: Reply,
+        ) -> Result<Response, StdError> {
+            deps.storage.set(REPLIED_KEY, &[1]);
+            Ok(Response::new())
+        }
+
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+    }
+
+    fn echo_manager() -> Manager {
+        let mut manager = Manager::new();
+        manager.register(Box::new(EchoModule)).unwrap();
+        manager
+    }
+
+    fn dispatch_to_echo(msg: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({ "module": "echo", "msg": msg })
+    }
+
+    fn instantiate_echo_contract(app: &mut App) -> cosmwasm_std::Addr {
+        let code_id = app.store_code(Box::new(ManagerContract::new(echo_manager())));
+        app.instantiate_contract(
+            code_id,
+            cosmwasm_std::Addr::unchecked("creator"),
+            &serde_json::json!({ "echo": {} }),
+            &[],
+            "echo manager",
+            Some("creator".to_string()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn instantiates_dispatches_a_submessage_and_runs_its_reply() {
+        let mut app = App::default();
+        let addr = instantiate_echo_contract(&mut app);
+
+        let replied: bool = app
+            .wrap()
+            .query_wasm_smart(
+                addr,
+                &serde_json::json!({ "dispatch": dispatch_to_echo(serde_json::json!({})) }),
+            )
+            .unwrap();
+        assert!(replied, "instantiate's submessage reply must have run");
+    }
+
+    #[test]
+    fn execute_sudo_and_migrate_all_dispatch_through_the_contract_adapter() {
+        let mut app = App::default();
+        let addr = instantiate_echo_contract(&mut app);
+        let creator = cosmwasm_std::Addr::unchecked("creator");
+
+        app.execute_contract(
+            creator.clone(),
+            addr.clone(),
+            &dispatch_to_echo(serde_json::json!({})),
+            &[],
+        )
+        .unwrap();
+
+        app.wasm_sudo(addr.clone(), &dispatch_to_echo(serde_json::json!({})))
+            .unwrap();
+
+        let new_code_id = app.store_code(Box::new(ManagerContract::new(echo_manager())));
+        app.migrate_contract(
+            creator,
+            addr,
+            &dispatch_to_echo(serde_json::json!({})),
+            new_code_id,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn query_reaches_the_aggregated_schema_envelope() {
+        let mut app = App::default();
+        let addr = instantiate_echo_contract(&mut app);
+
+        let schema: HashMap<String, serde_json::Value> = app
+            .wrap()
+            .query_wasm_smart(addr, &serde_json::json!({ "schema": {} }))
+            .unwrap();
+        assert!(schema.contains_key("echo"));
+    }
+}
@@ -0,0 +1,696 @@
+//! The `Manager`: a dynamic dispatcher that hosts a set of [`GenericModule`]s
+//! behind a single CosmWasm contract.
+
+use crate::module::{GenericModule, ModuleSchema};
+use crate::response::Response;
+use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Reply, StdError, StdResult};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Number of high bits of a reply `u64` id reserved for the registering
+/// module's index. The remaining low bits are left for the module's own,
+/// module-local reply ids.
+///
+/// CosmWasm's reply id space is a single flat `u64` shared by the whole
+/// contract, so a `Manager` hosting several modules has to partition it:
+/// the top `MODULE_INDEX_BITS` bits identify which module a reply belongs
+/// to, and the low `MODULE_LOCAL_BITS` bits are the id the module itself
+/// chose when it built its `SubMsg`.
+const MODULE_INDEX_BITS: u32 = 16;
+const MODULE_LOCAL_BITS: u32 = u64::BITS - MODULE_INDEX_BITS;
+const MODULE_LOCAL_MASK: u64 = (1 << MODULE_LOCAL_BITS) - 1;
+
+/// Dispatches instantiate/execute/query/reply calls to a set of registered
+/// [`GenericModule`]s.
+///
+/// Modules are assigned an index when they are registered with
+/// [`Manager::register`]. That index is packed into the high bits of every
+/// reply id a module's submessages use (see [`MODULE_INDEX_BITS`]), so a
+/// `Reply` coming back into the contract can be routed to the module that
+/// dispatched the submessage without the modules needing to coordinate
+/// their id spaces with each other.
+#[derive(Default)]
+pub struct Manager {
+    modules: Vec<Box<dyn GenericModule>>,
+}
+
+impl Manager {
+    /// Creates an empty `Manager` with no registered modules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a module with the `Manager`, returning the index it was
+    /// assigned. [`Manager::instantiate`] runs modules in registration
+    /// order except where a module's dependencies have to come first, and
+    /// this index is the value packed into the high bits of the module's
+    /// reply ids.
+    ///
+    /// Errors if another registered module already has the same
+    /// [`GenericModule::name`] — names must be unique so that every module
+    /// lookup by name resolves to a single, unambiguous module.
+    pub fn register(&mut self, module: Box<dyn GenericModule>) -> StdResult<u64> {
+        let index = self.modules.len() as u64;
+        if index >= (1 << MODULE_INDEX_BITS) {
+            return Err(StdError::generic_err(
+                "Manager cannot register more modules than the reply id module index allows",
+            ));
+        }
+        if self.modules.iter().any(|m| m.name() == module.name()) {
+            return Err(StdError::generic_err(format!(
+                "a module named '{}' is already registered with this Manager",
+                module.name()
+            )));
+        }
+        self.modules.push(module);
+        Ok(index)
+    }
+
+    /// Packs a module index and a module-local reply id into the single
+    /// `u64` id CosmWasm tracks, erroring if `local_id` overflows the bits
+    /// reserved for it.
+    fn pack_reply_id(index: u64, local_id: u64) -> StdResult<u64> {
+        if local_id > MODULE_LOCAL_MASK {
+            return Err(StdError::generic_err(format!(
+                "reply id {local_id} overflows the {MODULE_LOCAL_BITS}-bit module-local id space"
+            )));
+        }
+        Ok((index << MODULE_LOCAL_BITS) | local_id)
+    }
+
+    /// Splits a packed reply id back into the module index and the
+    /// module-local id the module originally chose.
+    fn unpack_reply_id(id: u64) -> (u64, u64) {
+        (id >> MODULE_LOCAL_BITS, id & MODULE_LOCAL_MASK)
+    }
+
+    /// Rewrites the ids of any submessages on `response` so that replies to
+    /// them are routed back to the module at `index`.
+    fn namespace_response(index: u64, mut response: Response) -> StdResult<Response> {
+        for sub_msg in response.messages.iter_mut() {
+            sub_msg.id = Self::pack_reply_id(index, sub_msg.id)?;
+        }
+        Ok(response)
+    }
+
+    /// Dispatches a submessage `Reply` to the module that originally sent
+    /// it, restoring the module-local id the module used when it built the
+    /// `SubMsg`.
+    pub fn reply(
+        &mut self,
+        deps: &mut DepsMut,
+        env: Env,
+        mut reply: Reply,
+    ) -> Result<Response, String> {
+        let (index, local_id) = Self::unpack_reply_id(reply.id);
+        let module = self
+            .modules
+            .get_mut(index as usize)
+            .ok_or_else(|| format!("no module registered for reply id {}", reply.id))?;
+        reply.id = local_id;
+        let response = module.reply_value(deps, env, reply)?;
+        Self::namespace_response(index, response).map_err(|e| e.to_string())
+    }
+
+    /// Validates that every registered module's declared
+    /// [`GenericModule::dependencies`] are themselves registered, then
+    /// returns the registration indices of all modules ordered so that a
+    /// module always appears after every module it depends on.
+    fn topological_order(&self) -> Result<Vec<usize>, String> {
+        let indices_by_name: HashMap<&'static str, usize> = self
+            .modules
+            .iter()
+            .enumerate()
+            .map(|(index, module)| (module.name(), index))
+            .collect();
+
+        for module in &self.modules {
+            for dependency in module.dependencies() {
+                if !indices_by_name.contains_key(dependency) {
+                    return Err(format!(
+                        "module '{}' depends on '{}', which is not registered with this Manager",
+                        module.name(),
+                        dependency
+                    ));
+                }
+            }
+        }
+
+        let mut order = Vec::with_capacity(self.modules.len());
+        let mut visited = vec![false; self.modules.len()];
+        let mut visiting = vec![false; self.modules.len()];
+        for index in 0..self.modules.len() {
+            self.visit_dependencies(
+                index,
+                &indices_by_name,
+                &mut visited,
+                &mut visiting,
+                &mut order,
+            )?;
+        }
+        Ok(order)
+    }
+
+    /// Depth-first visit used by [`Manager::topological_order`]. Pushes
+    /// `index` onto `order` only after every module it depends on has
+    /// already been pushed.
+    fn visit_dependencies(
+        &self,
+        index: usize,
+        indices_by_name: &HashMap<&'static str, usize>,
+        visited: &mut [bool],
+        visiting: &mut [bool],
+        order: &mut Vec<usize>,
+    ) -> Result<(), String> {
+        if visited[index] {
+            return Ok(());
+        }
+        if visiting[index] {
+            return Err(format!(
+                "dependency cycle detected involving module '{}'",
+                self.modules[index].name()
+            ));
+        }
+        visiting[index] = true;
+        for dependency in self.modules[index].dependencies() {
+            self.visit_dependencies(
+                indices_by_name[dependency],
+                indices_by_name,
+                visited,
+                visiting,
+                order,
+            )?;
+        }
+        visiting[index] = false;
+        visited[index] = true;
+        order.push(index);
+        Ok(())
+    }
+
+    /// Instantiates every registered module in dependency order.
+    ///
+    /// `msgs` maps a module's [`GenericModule::name`] to the instantiate
+    /// message it should receive; a module with no entry is instantiated
+    /// with `Value::Null`. Fails with a clear error if any module's
+    /// declared dependencies are missing from the registered set, before
+    /// any module is instantiated.
+    pub fn instantiate(
+        &mut self,
+        deps: &mut DepsMut,
+        env: &Env,
+        info: &MessageInfo,
+        msgs: &HashMap<String, Value>,
+    ) -> Result<Response, String> {
+        let order = self.topological_order()?;
+        let mut response = Response::new();
+        for index in order {
+            let module = &mut self.modules[index];
+            let msg = msgs.get(module.name()).cloned().unwrap_or(Value::Null);
+            let module_response = module.instantiate_value(deps, env, info, &msg)?;
+            let module_response =
+                Self::namespace_response(index as u64, module_response).map_err(|e| e.to_string())?;
+            response.messages.extend(module_response.messages);
+            response.attributes.extend(module_response.attributes);
+        }
+        Ok(response)
+    }
+
+    /// Finds the registration index of the module named `name`.
+    fn index_of(&self, name: &str) -> Result<usize, String> {
+        self.modules
+            .iter()
+            .position(|module| module.name() == name)
+            .ok_or_else(|| format!("no module named '{name}' is registered with this Manager"))
+    }
+
+    /// Dispatches an execute message to the module named `module_name`,
+    /// running its [`GenericModule::execute_guard`] first and aborting
+    /// without calling `execute` if the guard returns `Err`.
+    pub fn execute(
+        &mut self,
+        deps: &mut DepsMut,
+        env: Env,
+        info: MessageInfo,
+        module_name: &str,
+        msg: &Value,
+    ) -> Result<Response, String> {
+        let index = self.index_of(module_name)?;
+        let module = &mut self.modules[index];
+        module.execute_guard_value(&deps.as_ref(), &env, &info)?;
+        let response = module.execute_value(deps, env, info, msg)?;
+        Self::namespace_response(index as u64, response).map_err(|e| e.to_string())
+    }
+
+    /// Dispatches a query to the module named `module_name`.
+    pub fn query(
+        &self,
+        deps: &Deps,
+        env: Env,
+        module_name: &str,
+        msg: &Value,
+    ) -> StdResult<Binary> {
+        let index = self.index_of(module_name).map_err(StdError::generic_err)?;
+        self.modules[index].query_value(deps, env, msg)
+    }
+
+    /// Dispatches a sudo message to the module named `module_name`.
+    pub fn sudo(
+        &mut self,
+        deps: &mut DepsMut,
+        env: Env,
+        module_name: &str,
+        msg: &Value,
+    ) -> Result<Response, String> {
+        let index = self.index_of(module_name)?;
+        let response = self.modules[index].sudo_value(deps, env, msg)?;
+        Self::namespace_response(index as u64, response).map_err(|e| e.to_string())
+    }
+
+    /// Dispatches a migrate message to the module named `module_name`.
+    pub fn migrate(
+        &mut self,
+        deps: &mut DepsMut,
+        env: Env,
+        module_name: &str,
+        msg: &Value,
+    ) -> Result<Response, String> {
+        let index = self.index_of(module_name)?;
+        let response = self.modules[index].migrate_value(deps, env, msg)?;
+        Self::namespace_response(index as u64, response).map_err(|e| e.to_string())
+    }
+
+    /// Returns the merged JSON schema of every module registered with this
+    /// `Manager`, keyed by [`GenericModule::name`]. Backs a query so
+    /// clients can discover the message shapes of a dynamically dispatched
+    /// contract the same way `cosmwasm-schema` lets them discover a
+    /// monolithic contract's.
+    pub fn schema(&self) -> HashMap<&'static str, ModuleSchema> {
+        self.modules
+            .iter()
+            .map(|module| (module.name(), module.schema()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::Module;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{CosmosMsg, Empty, SubMsg, SubMsgResponse, SubMsgResult};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// A [`Module`] whose `instantiate` optionally dispatches a single
+    /// submessage with a caller-chosen local reply id, so tests can assert
+    /// on how the `Manager` namespaces and routes that id. `reply` records
+    /// the (already-unpacked) local id it was called with into
+    /// `last_reply_local_id`, shared with the test so it can be inspected
+    /// after the module has been moved into a `Manager`.
+    struct FakeModule {
+        name: &'static str,
+        dependencies: &'static [&'static str],
+        instantiate_reply_id: Option<u64>,
+        sudo_reply_id: Option<u64>,
+        migrate_reply_id: Option<u64>,
+        execute_guard_err: bool,
+        execute_count: Rc<Cell<u32>>,
+        last_reply_local_id: Rc<Cell<Option<u64>>>,
+    }
+
+    impl Module for FakeModule {
+        type InstantiateMsg = Empty;
+        type ExecuteMsg = Empty;
+        type QueryMsg = Empty;
+        type QueryResp = Empty;
+        type Error = StdError;
+        type SudoMsg = Empty;
+        type MigrateMsg = Empty;
+
+        fn instantiate(
+            &mut self,
+            _deps: &mut DepsMut,
+            _env: &Env,
+            _info: &MessageInfo,
+            _msg: Empty,
+        ) -> Result<Response, StdError> {
+            let response = match self.instantiate_reply_id {
+                Some(local_id) => Response::new()
+                    .add_submessage(SubMsg::reply_always(CosmosMsg::Custom(Empty {}), local_id)),
+                None => Response::new(),
+            };
+            Ok(response)
+        }
+
+        fn execute(
+            &mut self,
+            _deps: &mut DepsMut,
+            _env: Env,
+            _info: MessageInfo,
+            _msg: Empty,
+        ) -> Result<Response, StdError> {
+            self.execute_count.set(self.execute_count.get() + 1);
+            Ok(Response::new())
+        }
+
+        fn query(&self, _deps: &Deps, _env: Env, _msg: Empty) -> Result<Empty, StdError> {
+            Ok(Empty {})
+        }
+
+        fn execute_guard(
+            &self,
+            _deps: &Deps,
+            _env: &Env,
+            _info: &MessageInfo,
+        ) -> Result<(), StdError> {
+            if self.execute_guard_err {
+                Err(StdError::generic_err("execute guard rejected the call"))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn sudo(
+            &mut self,
+            _deps: &mut DepsMut,
+            _env: Env,
+            _msg: Empty,
+        ) -> Result<Response, StdError> {
+            let response = match self.sudo_reply_id {
+                Some(local_id) => Response::new()
+                    .add_submessage(SubMsg::reply_always(CosmosMsg::Custom(Empty {}), local_id)),
+                None => Response::new(),
+            };
+            Ok(response)
+        }
+
+        fn migrate(
+            &mut self,
+            _deps: &mut DepsMut,
+            _env: Env,
+            _msg: Empty,
+        ) -> Result<Response, StdError> {
+            let response = match self.migrate_reply_id {
+                Some(local_id) => Response::new()
+                    .add_submessage(SubMsg::reply_always(CosmosMsg::Custom(Empty {}), local_id)),
+                None => Response::new(),
+            };
+            Ok(response)
+        }
+
+        fn reply(
+            &mut self,
+            _deps: &mut DepsMut,
+            _env: Env,
+            reply: Reply,
+        ) -> Result<Response, StdError> {
+            self.last_reply_local_id.set(Some(reply.id));
+            Ok(Response::new())
+        }
+
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn dependencies(&self) -> &[&'static str] {
+            self.dependencies
+        }
+    }
+
+    fn fake(name: &'static str, dependencies: &'static [&'static str]) -> FakeModule {
+        FakeModule {
+            name,
+            dependencies,
+            instantiate_reply_id: None,
+            sudo_reply_id: None,
+            migrate_reply_id: None,
+            execute_guard_err: false,
+            execute_count: Rc::new(Cell::new(0)),
+            last_reply_local_id: Rc::new(Cell::new(None)),
+        }
+    }
+
+    /// Builds a `Reply` with the given local id and an empty success result,
+    /// as if a submessage the module dispatched had just completed.
+    fn fake_reply(local_id: u64) -> Reply {
+        Reply {
+            id: local_id,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        }
+    }
+
+    fn manager_with(modules: Vec<FakeModule>) -> Manager {
+        let mut manager = Manager::new();
+        for module in modules {
+            manager.register(Box::new(module)).unwrap();
+        }
+        manager
+    }
+
+    #[test]
+    fn reply_id_round_trips_through_pack_and_unpack() {
+        for index in [0u64, 1, 5, (1 << MODULE_INDEX_BITS) - 1] {
+            for local_id in [0u64, 1, 42, MODULE_LOCAL_MASK] {
+                let packed = Manager::pack_reply_id(index, local_id).unwrap();
+                assert_eq!(Manager::unpack_reply_id(packed), (index, local_id));
+            }
+        }
+    }
+
+    #[test]
+    fn pack_reply_id_rejects_local_id_overflowing_reserved_width() {
+        assert!(Manager::pack_reply_id(0, MODULE_LOCAL_MASK + 1).is_err());
+    }
+
+    #[test]
+    fn register_rejects_duplicate_module_names() {
+        let mut manager = Manager::new();
+        manager.register(Box::new(fake("admin", &[]))).unwrap();
+        assert!(manager.register(Box::new(fake("admin", &[]))).is_err());
+    }
+
+    #[test]
+    fn topological_order_puts_dependencies_first() {
+        let manager = manager_with(vec![fake("escrow", &["admin"]), fake("admin", &[])]);
+        let order = manager.topological_order().unwrap();
+        let admin_pos = order
+            .iter()
+            .position(|&i| i == manager.index_of("admin").unwrap())
+            .unwrap();
+        let escrow_pos = order
+            .iter()
+            .position(|&i| i == manager.index_of("escrow").unwrap())
+            .unwrap();
+        assert!(admin_pos < escrow_pos);
+    }
+
+    #[test]
+    fn topological_order_errors_on_missing_dependency() {
+        let manager = manager_with(vec![fake("escrow", &["admin"])]);
+        let err = manager.topological_order().unwrap_err();
+        assert!(err.contains("admin"));
+    }
+
+    #[test]
+    fn topological_order_errors_on_dependency_cycle() {
+        let manager = manager_with(vec![fake("a", &["b"]), fake("b", &["a"])]);
+        assert!(manager.topological_order().is_err());
+    }
+
+    #[test]
+    fn instantiate_namespaces_submessage_reply_ids_per_module() {
+        let mut manager = manager_with(vec![
+            fake("admin", &[]),
+            FakeModule {
+                instantiate_reply_id: Some(7),
+                ..fake("escrow", &["admin"])
+            },
+        ]);
+        let escrow_index = manager.index_of("escrow").unwrap() as u64;
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        let msgs = HashMap::from([
+            ("admin".to_string(), serde_json::json!({})),
+            ("escrow".to_string(), serde_json::json!({})),
+        ]);
+        let response = manager
+            .instantiate(&mut deps.as_mut(), &env, &info, &msgs)
+            .unwrap();
+
+        assert_eq!(response.messages.len(), 1);
+        let packed_id = response.messages[0].id;
+        assert_eq!(
+            Manager::unpack_reply_id(packed_id),
+            (escrow_index, 7),
+            "escrow's submessage id must be namespaced, not left as the raw local id"
+        );
+    }
+
+    #[test]
+    fn reply_routes_packed_id_to_owning_module_and_restores_local_id() {
+        let admin_replies = Rc::new(Cell::new(None));
+        let escrow_replies = Rc::new(Cell::new(None));
+        let mut manager = manager_with(vec![
+            FakeModule {
+                last_reply_local_id: admin_replies.clone(),
+                ..fake("admin", &[])
+            },
+            FakeModule {
+                last_reply_local_id: escrow_replies.clone(),
+                ..fake("escrow", &[])
+            },
+        ]);
+        let escrow_index = manager.index_of("escrow").unwrap() as u64;
+        let packed_id = Manager::pack_reply_id(escrow_index, 42).unwrap();
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        manager
+            .reply(&mut deps.as_mut(), env, fake_reply(packed_id))
+            .unwrap();
+
+        assert_eq!(
+            escrow_replies.get(),
+            Some(42),
+            "the reply must reach escrow with its local id restored"
+        );
+        assert_eq!(
+            admin_replies.get(),
+            None,
+            "a reply packed for escrow must not be delivered to admin"
+        );
+    }
+
+    #[test]
+    fn sudo_value_parses_msg_and_maps_errors_to_strings() {
+        let mut module = fake("admin", &[]);
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        assert!(module
+            .sudo_value(&mut deps.as_mut(), env.clone(), &serde_json::json!({}))
+            .is_ok());
+        assert!(module
+            .sudo_value(
+                &mut deps.as_mut(),
+                env,
+                &Value::String("not an Empty".into())
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn migrate_value_parses_msg_and_maps_errors_to_strings() {
+        let mut module = fake("admin", &[]);
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        assert!(module
+            .migrate_value(&mut deps.as_mut(), env.clone(), &serde_json::json!({}))
+            .is_ok());
+        assert!(module
+            .migrate_value(
+                &mut deps.as_mut(),
+                env,
+                &Value::String("not an Empty".into())
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn sudo_namespaces_submessage_reply_ids() {
+        let mut manager = manager_with(vec![
+            fake("admin", &[]),
+            FakeModule {
+                sudo_reply_id: Some(3),
+                ..fake("escrow", &["admin"])
+            },
+        ]);
+        let escrow_index = manager.index_of("escrow").unwrap() as u64;
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let response = manager
+            .sudo(&mut deps.as_mut(), env, "escrow", &serde_json::json!({}))
+            .unwrap();
+
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(
+            Manager::unpack_reply_id(response.messages[0].id),
+            (escrow_index, 3),
+            "escrow's sudo submessage id must be namespaced, not left as the raw local id"
+        );
+    }
+
+    #[test]
+    fn migrate_namespaces_submessage_reply_ids() {
+        let mut manager = manager_with(vec![
+            fake("admin", &[]),
+            FakeModule {
+                migrate_reply_id: Some(4),
+                ..fake("escrow", &["admin"])
+            },
+        ]);
+        let escrow_index = manager.index_of("escrow").unwrap() as u64;
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let response = manager
+            .migrate(&mut deps.as_mut(), env, "escrow", &serde_json::json!({}))
+            .unwrap();
+
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(
+            Manager::unpack_reply_id(response.messages[0].id),
+            (escrow_index, 4),
+            "escrow's migrate submessage id must be namespaced, not left as the raw local id"
+        );
+    }
+
+    #[test]
+    fn execute_aborts_without_calling_execute_when_guard_errs() {
+        let execute_count = Rc::new(Cell::new(0));
+        let mut manager = manager_with(vec![FakeModule {
+            execute_guard_err: true,
+            execute_count: execute_count.clone(),
+            ..fake("admin", &[])
+        }]);
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        let err = manager
+            .execute(&mut deps.as_mut(), env, info, "admin", &Value::Null)
+            .unwrap_err();
+
+        assert!(err.contains("execute guard rejected the call"));
+        assert_eq!(
+            execute_count.get(),
+            0,
+            "execute must not run when execute_guard returns Err"
+        );
+    }
+
+    #[test]
+    fn execute_calls_execute_when_guard_allows() {
+        let execute_count = Rc::new(Cell::new(0));
+        let mut manager = manager_with(vec![FakeModule {
+            execute_count: execute_count.clone(),
+            ..fake("admin", &[])
+        }]);
+
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        manager
+            .execute(&mut deps.as_mut(), env, info, "admin", &serde_json::json!({}))
+            .unwrap();
+
+        assert_eq!(execute_count.get(), 1);
+    }
+}
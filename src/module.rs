@@ -1,11 +1,35 @@
 //! Traits for reusable, composable CosmWasm modules.
 
 use crate::response::Response;
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, StdError, StdResult};
+use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Reply, StdError, StdResult};
+use schemars::{schema::RootSchema, JsonSchema};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fmt::Display;
 
+/// The JSON schemas for a module's message and response types, as produced
+/// by [`GenericModule::schema`].
+///
+/// Aggregating these the same way `cosmwasm-schema` does for monolithic
+/// contracts lets tooling generate typed clients for a module hosted by a
+/// `Manager`, even though the module is dispatched dynamically via
+/// `serde_json::Value` at runtime.
+#[derive(Serialize)]
+pub struct ModuleSchema {
+    /// The schema for [`Module::InstantiateMsg`].
+    pub instantiate: RootSchema,
+    /// The schema for [`Module::ExecuteMsg`].
+    pub execute: RootSchema,
+    /// The schema for [`Module::QueryMsg`].
+    pub query: RootSchema,
+    /// The schema for [`Module::QueryResp`].
+    pub response: RootSchema,
+    /// The schema for [`Module::SudoMsg`].
+    pub sudo: RootSchema,
+    /// The schema for [`Module::MigrateMsg`].
+    pub migrate: RootSchema,
+}
+
 /// A well typed CosmWasm module
 ///
 /// A module must implement instantiate, execute, and query handlers.
@@ -15,19 +39,27 @@ use std::fmt::Display;
 /// structs that implement Module.
 pub trait Module {
     /// The message sent to the module to instantiate its state.
-    type InstantiateMsg: for<'a> Deserialize<'a>;
+    type InstantiateMsg: for<'a> Deserialize<'a> + JsonSchema;
     /// The type of transaction messages this module can handle. For modules
     /// that support multiple types of transaction, this will often times be
     /// a sum type.
-    type ExecuteMsg: for<'a> Deserialize<'a>;
+    type ExecuteMsg: for<'a> Deserialize<'a> + JsonSchema;
     /// The type of query messages this module can handle. For modules that
     /// support multiple queries, this will often times be a sum type.
-    type QueryMsg: for<'a> Deserialize<'a>;
+    type QueryMsg: for<'a> Deserialize<'a> + JsonSchema;
     /// The response to queries dispatched to the module.
-    type QueryResp: Serialize;
+    type QueryResp: Serialize + JsonSchema;
     /// The type of errors this module can generate. This must implement
     /// Display for easy stringification.
     type Error: Display;
+    /// The message used to perform privileged, chain-triggered calls into
+    /// this module's state, e.g. via on-chain governance. Modules that
+    /// don't need a sudo entry point can set this to `cosmwasm_std::Empty`.
+    type SudoMsg: for<'a> Deserialize<'a> + JsonSchema;
+    /// The message used to migrate this module's state during a contract
+    /// code upgrade. Modules that don't need a migration step can set this
+    /// to `cosmwasm_std::Empty`.
+    type MigrateMsg: for<'a> Deserialize<'a> + JsonSchema;
 
     /// The instantiate handler for the module. When a Manager with this
     /// module registered is instantiated, this method may be called.
@@ -55,6 +87,77 @@ pub trait Module {
         env: Env,
         msg: Self::QueryMsg,
     ) -> Result<Self::QueryResp, Self::Error>;
+    /// The reply handler for this module. A `Manager` hosting this module
+    /// invokes this when a `Reply` comes back for a submessage the module
+    /// dispatched with `reply_on` set. Defaults to a no-op, since most
+    /// modules never dispatch submessages that request a reply.
+    ///
+    /// `reply.id` has already been restored to the module-local id the
+    /// module used when it built the `SubMsg` — the `Manager` is
+    /// responsible for stripping off the module index it packed into the
+    /// high bits before delivering the `Reply` here. See the `Manager`'s
+    /// documentation for the full bit split.
+    fn reply(
+        &mut self,
+        deps: &mut DepsMut,
+        env: Env,
+        reply: Reply,
+    ) -> Result<Response, Self::Error> {
+        let _ = (deps, env, reply);
+        Ok(Response::new())
+    }
+    /// The sudo handler for this module. A `Manager` hosting this module
+    /// invokes this when the chain delivers a privileged `SudoMsg` into the
+    /// contract, e.g. to resolve a governance proposal. Defaults to a
+    /// no-op, since most modules have no privileged entry point.
+    fn sudo(
+        &mut self,
+        deps: &mut DepsMut,
+        env: Env,
+        msg: Self::SudoMsg,
+    ) -> Result<Response, Self::Error> {
+        let _ = (deps, env, msg);
+        Ok(Response::new())
+    }
+    /// The migrate handler for this module. A `Manager` hosting this
+    /// module invokes this when the contract is migrated to new code, so
+    /// the module can reshape its own state. Defaults to a no-op, since
+    /// most modules have no state to migrate between versions.
+    fn migrate(
+        &mut self,
+        deps: &mut DepsMut,
+        env: Env,
+        msg: Self::MigrateMsg,
+    ) -> Result<Response, Self::Error> {
+        let _ = (deps, env, msg);
+        Ok(Response::new())
+    }
+    /// A stable, unique name identifying this module among the other
+    /// modules a `Manager` may host. Used both to address the module
+    /// (e.g. to route instantiate messages) and by [`Module::dependencies`]
+    /// to refer to other modules.
+    fn name(&self) -> &'static str;
+    /// The names of the other modules this module requires to be present
+    /// on the same `Manager`. Defaults to no dependencies. A `Manager`
+    /// validates this list against its registered modules before
+    /// instantiating any of them, and instantiates modules in dependency
+    /// order so that a dependency is always initialized before the
+    /// modules that require it.
+    fn dependencies(&self) -> &[&'static str] {
+        &[]
+    }
+    /// A pre-dispatch check the `Manager` runs before calling `execute` on
+    /// this module. Lets a module centralize cross-cutting checks — pause
+    /// state, rate limits, caller allow-lists — in one place instead of
+    /// repeating them at the top of every `execute` match arm. Defaults to
+    /// always allowing the call through.
+    ///
+    /// If this returns `Err`, the `Manager` aborts the whole execute
+    /// without calling `execute`.
+    fn execute_guard(&self, deps: &Deps, env: &Env, info: &MessageInfo) -> Result<(), Self::Error> {
+        let _ = (deps, env, info);
+        Ok(())
+    }
 }
 
 /// A dynamically typed module.
@@ -82,17 +185,52 @@ pub trait GenericModule {
     ) -> Result<Response, String>;
     /// A generic implementation of Module::query
     fn query_value(&self, deps: &Deps, env: Env, msg: &Value) -> StdResult<Binary>;
+    /// A generic implementation of Module::reply
+    fn reply_value(
+        &mut self,
+        deps: &mut DepsMut,
+        env: Env,
+        reply: Reply,
+    ) -> Result<Response, String>;
+    /// A generic implementation of Module::sudo
+    fn sudo_value(&mut self, deps: &mut DepsMut, env: Env, msg: &Value) -> Result<Response, String>;
+    /// A generic implementation of Module::migrate
+    fn migrate_value(
+        &mut self,
+        deps: &mut DepsMut,
+        env: Env,
+        msg: &Value,
+    ) -> Result<Response, String>;
+    /// A generic implementation of Module::name
+    fn name(&self) -> &'static str;
+    /// A generic implementation of Module::dependencies
+    fn dependencies(&self) -> &[&'static str];
+    /// A generic implementation of Module::execute_guard
+    fn execute_guard_value(&self, deps: &Deps, env: &Env, info: &MessageInfo) -> Result<(), String>;
+    /// Returns the JSON schemas for this module's instantiate, execute,
+    /// query, response, sudo and migrate types.
+    fn schema(&self) -> ModuleSchema;
 }
 
 /// An implementation of GenericModule for all valid implementations of Module.
-impl<T, A, B, C, D, E> GenericModule for T
+impl<T, A, B, C, D, E, F, G> GenericModule for T
 where
-    A: for<'de> Deserialize<'de>,
-    B: for<'de> Deserialize<'de>,
-    C: for<'de> Deserialize<'de>,
-    D: Serialize,
+    A: for<'de> Deserialize<'de> + JsonSchema,
+    B: for<'de> Deserialize<'de> + JsonSchema,
+    C: for<'de> Deserialize<'de> + JsonSchema,
+    D: Serialize + JsonSchema,
     E: Display,
-    T: Module<InstantiateMsg = A, ExecuteMsg = B, QueryMsg = C, QueryResp = D, Error = E>,
+    F: for<'de> Deserialize<'de> + JsonSchema,
+    G: for<'de> Deserialize<'de> + JsonSchema,
+    T: Module<
+        InstantiateMsg = A,
+        ExecuteMsg = B,
+        QueryMsg = C,
+        QueryResp = D,
+        Error = E,
+        SudoMsg = F,
+        MigrateMsg = G,
+    >,
 {
     fn instantiate_value(
         &mut self,
@@ -126,4 +264,63 @@ where
             .map_err(|e| StdError::generic_err(e.to_string()))?;
         cosmwasm_std::to_binary(&res)
     }
+
+    fn reply_value(
+        &mut self,
+        deps: &mut DepsMut,
+        env: Env,
+        reply: Reply,
+    ) -> Result<Response, String> {
+        self.reply(deps, env, reply).map_err(|e| e.to_string())
+    }
+
+    fn sudo_value(
+        &mut self,
+        deps: &mut DepsMut,
+        env: Env,
+        msg: &Value,
+    ) -> Result<Response, String> {
+        let parsed_msg = serde_json::from_value(msg.clone()).map_err(|e| e.to_string())?;
+        self.sudo(deps, env, parsed_msg).map_err(|e| e.to_string())
+    }
+
+    fn migrate_value(
+        &mut self,
+        deps: &mut DepsMut,
+        env: Env,
+        msg: &Value,
+    ) -> Result<Response, String> {
+        let parsed_msg = serde_json::from_value(msg.clone()).map_err(|e| e.to_string())?;
+        self.migrate(deps, env, parsed_msg)
+            .map_err(|e| e.to_string())
+    }
+
+    fn name(&self) -> &'static str {
+        Module::name(self)
+    }
+
+    fn dependencies(&self) -> &[&'static str] {
+        Module::dependencies(self)
+    }
+
+    fn execute_guard_value(
+        &self,
+        deps: &Deps,
+        env: &Env,
+        info: &MessageInfo,
+    ) -> Result<(), String> {
+        self.execute_guard(deps, env, info)
+            .map_err(|e| e.to_string())
+    }
+
+    fn schema(&self) -> ModuleSchema {
+        ModuleSchema {
+            instantiate: schemars::schema_for!(A),
+            execute: schemars::schema_for!(B),
+            query: schemars::schema_for!(C),
+            response: schemars::schema_for!(D),
+            sudo: schemars::schema_for!(F),
+            migrate: schemars::schema_for!(G),
+        }
+    }
 }